@@ -0,0 +1,67 @@
+//! Shared error type for VCF/BCF parsing, the binary sidecar, its index, and its
+//! storage sinks.
+//!
+//! This used to be four near-identical ad-hoc enums (`VcfParseError`,
+//! `Error::MyErr`, `OutfileError::General`, and `VcfError` with a literal `TmpErr`
+//! placeholder), none of which carried enough context to say *where* a load failed.
+//! Collapsing them into one `thiserror` enum lets a bad record surface its
+//! chromosome/position and the offending INFO field instead of panicking or
+//! returning an opaque variant.
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum VcfError {
+    #[error("unsupported file extension for variant input")]
+    UnsupportedFiletype,
+
+    #[error("failed to read variant header: {0}")]
+    Header(#[source] std::io::Error),
+
+    #[error("failed to read variant record: {0}")]
+    Record(#[source] std::io::Error),
+
+    #[error("{0}: missing or invalid variant start position")]
+    MissingPosition(String),
+
+    #[error("unknown variation-type id `{0}`")]
+    UnknownVariationType(u8),
+
+    #[error("{chrom}:{pos} INFO field `{field}`: {message}")]
+    InfoField {
+        chrom: String,
+        pos: u32,
+        field: String,
+        message: String,
+    },
+
+    #[error("{chrom}:{pos}: malformed VRS allele ID `{vrs_id}`")]
+    MalformedVrsId {
+        chrom: String,
+        pos: u32,
+        vrs_id: String,
+    },
+
+    #[error("index file is missing the expected magic bytes")]
+    BadMagic,
+
+    #[error("index file is truncated or corrupt")]
+    TruncatedIndex,
+
+    #[error("no indexed records for chromosome `{0}`")]
+    UnknownChrom(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Utf8(#[from] std::str::Utf8Error),
+
+    #[error(transparent)]
+    FromUtf8(#[from] std::string::FromUtf8Error),
+
+    #[error(transparent)]
+    ParseInt(#[from] std::num::ParseIntError),
+
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+}
@@ -0,0 +1,398 @@
+//! Coordinate index over the binary VRS sidecar written by [`crate::vcf::load_vcf`].
+//!
+//! Each reference sequence's record block is range-binned using the standard
+//! UCSC/tabix binning recurrence, so a `chrom:start-end` query only has to pull the
+//! handful of candidate offsets that could overlap the region instead of scanning
+//! the whole block.
+
+use crate::error::VcfError;
+use crate::vcf::{read_record_at, vrs_id_from_parts, VrsAlleleAttrs, FORMAT_VERSION};
+use futures::stream::{self, Stream};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
+
+const INDEX_MAGIC: &[u8; 4] = b"VRSI";
+
+/// One record's position within a chromosome block: its `pos` and its byte offset
+/// in the data file (to seek directly to it).
+#[derive(Debug, Clone, Copy)]
+struct IndexEntry {
+    pos: u32,
+    offset: u64,
+}
+
+/// Everything the index knows about one reference sequence's record block.
+#[derive(Debug)]
+pub(crate) struct ChromBlock {
+    chrom: String,
+    entries: Vec<IndexEntry>,
+    bins: HashMap<u32, Vec<u32>>, // bin ID -> indices into `entries`
+}
+
+/// UCSC binning recurrence (as used by BAM/tabix/CSI): the smallest bin that fully
+/// contains the half-open interval `[start, end)`.
+fn reg2bin(start: u32, end: u32) -> u32 {
+    let end = end.saturating_sub(1);
+    if start >> 14 == end >> 14 {
+        return ((1 << 15) - 1) / 7 + (start >> 14);
+    }
+    if start >> 17 == end >> 17 {
+        return ((1 << 12) - 1) / 7 + (start >> 17);
+    }
+    if start >> 20 == end >> 20 {
+        return ((1 << 9) - 1) / 7 + (start >> 20);
+    }
+    if start >> 23 == end >> 23 {
+        return ((1 << 6) - 1) / 7 + (start >> 23);
+    }
+    if start >> 26 == end >> 26 {
+        return ((1 << 3) - 1) / 7 + (start >> 26);
+    }
+    0
+}
+
+/// UCSC query-side recurrence: all bins across every level that could overlap the
+/// half-open interval `[start, end)`.
+fn reg2bins(start: u32, end: u32) -> Vec<u32> {
+    let end = end.saturating_sub(1);
+    let mut bins = vec![0];
+    let level_offsets_and_shifts = [(1u32, 26), (9, 23), (73, 20), (585, 17), (4681, 14)];
+    for (level_offset, shift) in level_offsets_and_shifts {
+        let lo = level_offset + (start >> shift);
+        let hi = level_offset + (end >> shift);
+        bins.extend(lo..=hi);
+    }
+    bins
+}
+
+/// Records one VRS allele's coordinates in the in-progress index, opening a new
+/// [`ChromBlock`] the first time a chromosome is seen.
+pub(crate) fn add_record(
+    blocks: &mut Vec<ChromBlock>,
+    chrom_block_idx: &mut HashMap<String, usize>,
+    chrom: &str,
+    record_offset: u64,
+    pos: u32,
+    vrs_start: i32,
+    vrs_end: i32,
+) {
+    let block_idx = *chrom_block_idx.entry(chrom.to_string()).or_insert_with(|| {
+        blocks.push(ChromBlock {
+            chrom: chrom.to_string(),
+            entries: Vec::new(),
+            bins: HashMap::new(),
+        });
+        blocks.len() - 1
+    });
+
+    let block = &mut blocks[block_idx];
+    let entry_idx = block.entries.len() as u32;
+    block.entries.push(IndexEntry {
+        pos,
+        offset: record_offset,
+    });
+
+    let bin = reg2bin(vrs_start.max(0) as u32, vrs_end.max(0) as u32);
+    block.bins.entry(bin).or_default().push(entry_idx);
+}
+
+/// Writes the companion index file: magic bytes, format version, then per-chromosome
+/// blocks holding the position-sorted entry list and the bin -> entry-index table.
+pub(crate) async fn write_index(index_path: &Path, blocks: &[ChromBlock]) -> Result<(), VcfError> {
+    use crate::vcf::ToBytesLe;
+
+    let mut buf = Vec::new();
+    buf.extend_from_slice(INDEX_MAGIC);
+    buf.write_u8(FORMAT_VERSION);
+    buf.write_u16_le(blocks.len() as u16);
+
+    for block in blocks {
+        buf.write_u16_le(block.chrom.len() as u16);
+        buf.extend_from_slice(block.chrom.as_bytes());
+
+        buf.write_u32_le(block.entries.len() as u32);
+        for entry in &block.entries {
+            buf.write_u32_le(entry.pos);
+            buf.extend_from_slice(&entry.offset.to_le_bytes());
+        }
+
+        buf.write_u32_le(block.bins.len() as u32);
+        for (bin, entry_indices) in &block.bins {
+            buf.write_u32_le(*bin);
+            buf.write_u32_le(entry_indices.len() as u32);
+            for idx in entry_indices {
+                buf.write_u32_le(*idx);
+            }
+        }
+    }
+
+    let out_file = File::create(index_path).await?;
+    let mut writer = BufWriter::new(out_file);
+    writer.write_all(&buf).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+async fn read_index(index_path: &Path) -> Result<Vec<ChromBlock>, VcfError> {
+    let mut file = File::open(index_path).await?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).await?;
+
+    let mut cursor = 0usize;
+    let read_u8 = |buf: &[u8], cursor: &mut usize| -> Result<u8, VcfError> {
+        let val = *buf.get(*cursor).ok_or(VcfError::TruncatedIndex)?;
+        *cursor += 1;
+        Ok(val)
+    };
+    let read_u16 = |buf: &[u8], cursor: &mut usize| -> Result<u16, VcfError> {
+        let slice = buf
+            .get(*cursor..*cursor + 2)
+            .ok_or(VcfError::TruncatedIndex)?;
+        let val = u16::from_le_bytes(slice.try_into().unwrap());
+        *cursor += 2;
+        Ok(val)
+    };
+    let read_u32 = |buf: &[u8], cursor: &mut usize| -> Result<u32, VcfError> {
+        let slice = buf
+            .get(*cursor..*cursor + 4)
+            .ok_or(VcfError::TruncatedIndex)?;
+        let val = u32::from_le_bytes(slice.try_into().unwrap());
+        *cursor += 4;
+        Ok(val)
+    };
+    let read_u64 = |buf: &[u8], cursor: &mut usize| -> Result<u64, VcfError> {
+        let slice = buf
+            .get(*cursor..*cursor + 8)
+            .ok_or(VcfError::TruncatedIndex)?;
+        let val = u64::from_le_bytes(slice.try_into().unwrap());
+        *cursor += 8;
+        Ok(val)
+    };
+
+    if buf.get(0..4) != Some(INDEX_MAGIC.as_slice()) {
+        return Err(VcfError::BadMagic);
+    }
+    cursor += 4;
+    let _version = read_u8(&buf, &mut cursor)?;
+
+    let block_count = read_u16(&buf, &mut cursor)?;
+    let mut blocks = Vec::with_capacity(block_count as usize);
+
+    for _ in 0..block_count {
+        let chrom_len = read_u16(&buf, &mut cursor)? as usize;
+        let chrom_bytes = buf
+            .get(cursor..cursor + chrom_len)
+            .ok_or(VcfError::TruncatedIndex)?;
+        let chrom = String::from_utf8(chrom_bytes.to_vec())?;
+        cursor += chrom_len;
+
+        let entry_count = read_u32(&buf, &mut cursor)?;
+        let mut entries = Vec::with_capacity(entry_count as usize);
+        for _ in 0..entry_count {
+            let pos = read_u32(&buf, &mut cursor)?;
+            let offset = read_u64(&buf, &mut cursor)?;
+            entries.push(IndexEntry { pos, offset });
+        }
+
+        let bin_count = read_u32(&buf, &mut cursor)?;
+        let mut bins = HashMap::with_capacity(bin_count as usize);
+        for _ in 0..bin_count {
+            let bin = read_u32(&buf, &mut cursor)?;
+            let idx_count = read_u32(&buf, &mut cursor)?;
+            let mut idxs = Vec::with_capacity(idx_count as usize);
+            for _ in 0..idx_count {
+                idxs.push(read_u32(&buf, &mut cursor)?);
+            }
+            bins.insert(bin, idxs);
+        }
+
+        blocks.push(ChromBlock {
+            chrom,
+            entries,
+            bins,
+        });
+    }
+
+    Ok(blocks)
+}
+
+/// Returns the companion index path for a data file, e.g. `output.bin` ->
+/// `output.bin.idx`.
+pub fn companion_index_path(data_path: &Path) -> PathBuf {
+    let mut os_path = data_path.as_os_str().to_os_string();
+    os_path.push(".idx");
+    PathBuf::from(os_path)
+}
+
+/// Looks up every VRS allele overlapping `chrom:start-end` without a full scan.
+///
+/// Candidate bins for the query interval are computed with the standard UCSC
+/// recurrence, narrowing the search to the entries those bins reference. Every
+/// candidate is read and overlap-checked directly: VRS left-normalization can shift
+/// a later record's start earlier than an earlier record's `pos`, so `pos` alone
+/// can't be used to prune or early-exit the candidate list -- only the `vrs_start`/
+/// `vrs_end` bounds can.
+pub async fn query(
+    data_path: PathBuf,
+    index_path: PathBuf,
+    chrom: String,
+    start: u32,
+    end: u32,
+) -> Result<impl Stream<Item = Result<VrsAlleleAttrs, VcfError>>, VcfError> {
+    let blocks = read_index(&index_path).await?;
+    let block = blocks
+        .into_iter()
+        .find(|block| block.chrom == chrom)
+        .ok_or_else(|| VcfError::UnknownChrom(chrom.clone()))?;
+
+    let mut candidate_idxs: Vec<u32> = reg2bins(start, end)
+        .into_iter()
+        .filter_map(|bin| block.bins.get(&bin))
+        .flatten()
+        .copied()
+        .collect();
+    candidate_idxs.sort_unstable();
+    candidate_idxs.dedup();
+
+    let mut file = File::open(&data_path).await?;
+    let mut results = Vec::new();
+    for &idx in &candidate_idxs {
+        let entry = block
+            .entries
+            .get(idx as usize)
+            .ok_or(VcfError::TruncatedIndex)?;
+        let (data, _chrom_id) = read_record_at(&mut file, entry.offset).await?;
+
+        // Both the query window and a VRS allele's coordinates are the half-open
+        // interval [start, end), so a shared boundary point is not an overlap.
+        if data.vrs_start >= end as i32 {
+            continue;
+        }
+        if data.vrs_end <= start as i32 {
+            continue;
+        }
+
+        let vrs_id = vrs_id_from_parts(data.vartype_id, &data.vrs_digest)?;
+        results.push(Ok(VrsAlleleAttrs {
+            vrs_id,
+            vrs_start: data.vrs_start,
+            vrs_end: data.vrs_end,
+            vrs_state: data.vrs_state,
+        }));
+    }
+
+    Ok(stream::iter(results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vcf::{encode_record, FileData};
+    use futures::StreamExt;
+
+    /// A query whose window starts inside a multi-base variant (the `vrs_start` is
+    /// left of `pos`, as left-normalization can produce) must still see it as an
+    /// overlap rather than being pruned by a `pos`-based skip-ahead.
+    #[tokio::test]
+    async fn query_finds_record_whose_window_starts_inside_a_deletion() {
+        let dir = std::env::temp_dir().join(format!("vrs-index-query-test-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let data_path = dir.join("data.bin");
+        let index_path = dir.join("data.bin.idx");
+
+        let data = FileData {
+            chrom: "chr1".to_string(),
+            pos: 100,
+            uri_id: 0,
+            vartype_id: 1,
+            vrs_digest: *b"abcdefghijklmnopqrstuvwxyz012345",
+            vrs_start: 95,
+            vrs_end: 105,
+            vrs_state: "A".to_string(),
+        };
+        let record_bytes = encode_record(&data, 0).unwrap();
+        tokio::fs::write(&data_path, &record_bytes).await.unwrap();
+
+        let mut blocks = Vec::new();
+        let mut chrom_block_idx = HashMap::new();
+        add_record(
+            &mut blocks,
+            &mut chrom_block_idx,
+            "chr1",
+            0,
+            data.pos,
+            data.vrs_start,
+            data.vrs_end,
+        );
+        write_index(&index_path, &blocks).await.unwrap();
+
+        let results: Vec<_> = query(data_path.clone(), index_path.clone(), "chr1".to_string(), 102, 110)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+
+        assert_eq!(results.len(), 1);
+        let allele = results.into_iter().next().unwrap().unwrap();
+        assert_eq!(allele.vrs_start, 95);
+        assert_eq!(allele.vrs_end, 105);
+    }
+
+    #[test]
+    fn reg2bin_contains_the_whole_interval() {
+        let bin = reg2bin(1000, 2000);
+        assert!(reg2bins(1000, 2000).contains(&bin));
+    }
+
+    /// A query window is the half-open interval `[start, end)`, same as a VRS
+    /// allele's own `[vrs_start, vrs_end)` coordinates -- a shared boundary point is
+    /// not an overlap.
+    #[tokio::test]
+    async fn query_excludes_a_record_that_only_touches_the_query_boundary() {
+        let dir = std::env::temp_dir().join(format!("vrs-index-boundary-test-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let data_path = dir.join("data.bin");
+        let index_path = dir.join("data.bin.idx");
+
+        let data = FileData {
+            chrom: "chr1".to_string(),
+            pos: 100,
+            uri_id: 0,
+            vartype_id: 1,
+            vrs_digest: *b"abcdefghijklmnopqrstuvwxyz012345",
+            vrs_start: 100,
+            vrs_end: 110,
+            vrs_state: "A".to_string(),
+        };
+        let record_bytes = encode_record(&data, 0).unwrap();
+        tokio::fs::write(&data_path, &record_bytes).await.unwrap();
+
+        let mut blocks = Vec::new();
+        let mut chrom_block_idx = HashMap::new();
+        add_record(
+            &mut blocks,
+            &mut chrom_block_idx,
+            "chr1",
+            0,
+            data.pos,
+            data.vrs_start,
+            data.vrs_end,
+        );
+        write_index(&index_path, &blocks).await.unwrap();
+
+        // Query window [110, 120) starts exactly where the record ends -- no overlap.
+        let results: Vec<_> = query(data_path.clone(), index_path.clone(), "chr1".to_string(), 110, 120)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+
+        assert!(results.is_empty());
+    }
+}
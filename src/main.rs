@@ -1,5 +1,9 @@
 use std::path::PathBuf;
+pub mod error;
+pub mod index;
+pub mod sink;
 pub mod vcf;
+use sink::FileSink;
 use tokio;
 
 #[tokio::main]
@@ -7,6 +11,7 @@ async fn main() {
     let path = PathBuf::from(
         r"/Users/jss009/code/vrs_anvil_toolkit/u08_release_data/gregor_consortium_u06_sorted_chr1_V2_VT_VEP_VRS.vcf.gz",
     );
-    let _ = vcf::load_vcf(path, None, PathBuf::from("output.txt")).await;
+    let sink = FileSink::new(PathBuf::from("output.bin"), 1, None);
+    let _ = vcf::load_vcf(path, sink).await;
     ()
 }
@@ -0,0 +1,231 @@
+//! Pluggable storage backends for [`crate::vcf::load_vcf`].
+//!
+//! `load_vcf` only knows it has a [`VrsSink`] to hand alleles to; it's otherwise
+//! agnostic of whether they land in the binary sidecar ([`FileSink`]) or a SQLite
+//! database ([`SqliteSink`]).
+
+use crate::error::VcfError;
+use crate::index::{self, ChromBlock};
+use crate::vcf::{encode_header, encode_record, vrs_id_from_parts, FileData};
+use async_trait::async_trait;
+use futures::{Stream, TryStreamExt};
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+/// Where parsed VRS alleles get written as `load_vcf` streams them off a VCF/BCF file.
+#[async_trait]
+pub trait VrsSink {
+    async fn write_allele(&mut self, data: &FileData) -> Result<(), VcfError>;
+    async fn finalize(&mut self) -> Result<(), VcfError>;
+}
+
+/// Writes the binary sidecar (and its companion coordinate index) that
+/// `load_vcf` originally wrote directly.
+///
+/// Records are buffered until `finalize`, since the header's chrom dictionary isn't
+/// complete -- and record byte offsets aren't knowable -- until every chromosome in
+/// the input has been seen.
+pub struct FileSink {
+    output_path: PathBuf,
+    uri_id: u8,
+    uri: String,
+    records: Vec<FileData>,
+}
+
+impl FileSink {
+    pub fn new(output_path: PathBuf, uri_id: u8, uri: Option<String>) -> Self {
+        Self {
+            output_path,
+            uri_id,
+            uri: uri.unwrap_or_default(),
+            records: Vec::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl VrsSink for FileSink {
+    async fn write_allele(&mut self, data: &FileData) -> Result<(), VcfError> {
+        let mut data = data.clone();
+        data.uri_id = self.uri_id;
+        self.records.push(data);
+        Ok(())
+    }
+
+    async fn finalize(&mut self) -> Result<(), VcfError> {
+        let mut chrom_ids: HashMap<String, u16> = HashMap::new();
+        let mut chrom_dict: Vec<(u16, String)> = Vec::new();
+        let mut blocks: Vec<ChromBlock> = Vec::new();
+        let mut chrom_block_idx: HashMap<String, usize> = HashMap::new();
+
+        let out_file = File::create(&self.output_path).await?;
+        let mut writer = BufWriter::new(out_file);
+
+        // The chrom dictionary has to be complete before the header can be encoded,
+        // so resolve every record's chrom_id up front.
+        let chrom_ids_by_record: Vec<u16> = self
+            .records
+            .iter()
+            .map(|data| {
+                *chrom_ids.entry(data.chrom.clone()).or_insert_with(|| {
+                    let id = chrom_dict.len() as u16;
+                    chrom_dict.push((id, data.chrom.clone()));
+                    id
+                })
+            })
+            .collect();
+
+        let uri_dict = vec![(self.uri_id, self.uri.clone())];
+        let header_bytes = encode_header(&uri_dict, &chrom_dict);
+        writer.write_all(&header_bytes).await?;
+
+        let mut offset = header_bytes.len() as u64;
+        for (data, chrom_id) in self.records.iter().zip(chrom_ids_by_record) {
+            let encoded = encode_record(data, chrom_id)?;
+            index::add_record(
+                &mut blocks,
+                &mut chrom_block_idx,
+                &data.chrom,
+                offset,
+                data.pos,
+                data.vrs_start,
+                data.vrs_end,
+            );
+            writer.write_all(&encoded).await?;
+            offset += encoded.len() as u64;
+        }
+
+        writer.flush().await?;
+
+        let index_path = index::companion_index_path(&self.output_path);
+        index::write_index(&index_path, &blocks).await
+    }
+}
+
+/// One row as stored in the `vrs_allele` table.
+#[derive(Debug, sqlx::FromRow)]
+pub struct SqliteAlleleRow {
+    pub vrs_id: String,
+    pub chrom: String,
+    pub pos: i64,
+    pub vrs_start: i32,
+    pub vrs_end: i32,
+    pub vrs_state: String,
+    pub last_accessed_at: i64,
+}
+
+/// SQLite-backed sink: batches inserts into one transaction per `batch_size` alleles
+/// rather than committing per row.
+///
+/// Mirrors a small repository surface on top of the table -- `rows_older_than` for
+/// streaming out stale entries, `record_access` to bump an entry's last-used
+/// timestamp, and `remove` to prune by key -- so downstream tooling can manage the
+/// store without reaching for raw SQL.
+pub struct SqliteSink {
+    pool: SqlitePool,
+    batch_size: usize,
+    pending: Vec<FileData>,
+}
+
+impl SqliteSink {
+    pub async fn connect(database_url: &str, batch_size: usize) -> Result<Self, VcfError> {
+        let pool = SqlitePool::connect(database_url).await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS vrs_allele (
+                vrs_id TEXT PRIMARY KEY,
+                chrom TEXT NOT NULL,
+                pos INTEGER NOT NULL,
+                vrs_start INTEGER NOT NULL,
+                vrs_end INTEGER NOT NULL,
+                vrs_state TEXT NOT NULL,
+                last_accessed_at INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        Ok(Self {
+            pool,
+            batch_size,
+            pending: Vec::new(),
+        })
+    }
+
+    async fn flush_batch(&mut self) -> Result<(), VcfError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        for data in self.pending.drain(..) {
+            let vrs_id = vrs_id_from_parts(data.vartype_id, &data.vrs_digest)?;
+            sqlx::query(
+                "INSERT OR REPLACE INTO vrs_allele
+                    (vrs_id, chrom, pos, vrs_start, vrs_end, vrs_state, last_accessed_at)
+                 VALUES (?, ?, ?, ?, ?, ?, strftime('%s', 'now'))",
+            )
+            .bind(vrs_id)
+            .bind(data.chrom)
+            .bind(data.pos)
+            .bind(data.vrs_start)
+            .bind(data.vrs_end)
+            .bind(data.vrs_state)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Streams rows whose `last_accessed_at` predates `before_unix`, oldest first.
+    pub fn rows_older_than(
+        &self,
+        before_unix: i64,
+    ) -> impl Stream<Item = Result<SqliteAlleleRow, VcfError>> + '_ {
+        sqlx::query_as::<_, SqliteAlleleRow>(
+            "SELECT vrs_id, chrom, pos, vrs_start, vrs_end, vrs_state, last_accessed_at
+             FROM vrs_allele
+             WHERE last_accessed_at < ?
+             ORDER BY last_accessed_at ASC",
+        )
+        .bind(before_unix)
+        .fetch(&self.pool)
+        .map_err(VcfError::from)
+    }
+
+    /// Bumps a row's `last_accessed_at` to now.
+    pub async fn record_access(&self, vrs_id: &str) -> Result<(), VcfError> {
+        sqlx::query("UPDATE vrs_allele SET last_accessed_at = strftime('%s', 'now') WHERE vrs_id = ?")
+            .bind(vrs_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Removes a row by its VRS ID.
+    pub async fn remove(&self, vrs_id: &str) -> Result<(), VcfError> {
+        sqlx::query("DELETE FROM vrs_allele WHERE vrs_id = ?")
+            .bind(vrs_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl VrsSink for SqliteSink {
+    async fn write_allele(&mut self, data: &FileData) -> Result<(), VcfError> {
+        self.pending.push(data.clone());
+        if self.pending.len() >= self.batch_size {
+            self.flush_batch().await?;
+        }
+        Ok(())
+    }
+
+    async fn finalize(&mut self) -> Result<(), VcfError> {
+        self.flush_batch().await
+    }
+}